@@ -1,17 +1,101 @@
 // MiniVault Binary Protocol Client for Rust
 
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::net::TcpStream;
-use std::time::Duration;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 const OP_GET: u8 = 0x01;
 const OP_SET: u8 = 0x02;
 const OP_DELETE: u8 = 0x03;
 const OP_HEALTH: u8 = 0x05;
 const OP_AUTH: u8 = 0x06;
+const OP_CHUNK_MANIFEST: u8 = 0x07;
+const OP_CHUNK_UPLOAD: u8 = 0x08;
+const OP_CHUNK_FINALIZE: u8 = 0x09;
 
 const STATUS_SUCCESS: u8 = 0x00;
+const STATUS_NOT_FOUND: u8 = 0x01;
+const STATUS_UNAUTHORIZED: u8 = 0x02;
+const STATUS_BAD_REQUEST: u8 = 0x03;
+
+/// Size of each chunk `set_chunked` splits a value into before hashing and
+/// uploading it.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+/// SHA-256 digest identifying a chunk by content.
+type ChunkHash = [u8; 32];
+
+/// Marks a SET value as compressed so `get` knows to reverse it; written as
+/// the first 4 bytes of the value whenever `Compression` is anything but
+/// `None`, since the server echoes value bytes back verbatim on GET.
+const COMPRESSED_MAGIC: &[u8; 4] = b"MVC1";
+
+/// Structured failure modes for the binary protocol, so callers can `match`
+/// on the `STATUS_*` byte a request failed with instead of string-matching
+/// a formatted message.
+#[derive(Debug)]
+pub enum MiniVaultError {
+    NotFound,
+    Unauthorized,
+    BadRequest,
+    ServerError { status: u8 },
+    Io(std::io::Error),
+    Serde(serde_json::Error),
+    Protocol(String),
+}
+
+impl MiniVaultError {
+    fn from_status(status: u8) -> Self {
+        match status {
+            STATUS_NOT_FOUND => MiniVaultError::NotFound,
+            STATUS_UNAUTHORIZED => MiniVaultError::Unauthorized,
+            STATUS_BAD_REQUEST => MiniVaultError::BadRequest,
+            other => MiniVaultError::ServerError { status: other },
+        }
+    }
+}
+
+impl std::fmt::Display for MiniVaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MiniVaultError::NotFound => write!(f, "key not found"),
+            MiniVaultError::Unauthorized => write!(f, "unauthorized"),
+            MiniVaultError::BadRequest => write!(f, "bad request"),
+            MiniVaultError::ServerError { status } => {
+                write!(f, "server error: status=0x{:02x}", status)
+            }
+            MiniVaultError::Io(err) => write!(f, "io error: {}", err),
+            MiniVaultError::Serde(err) => write!(f, "serialization error: {}", err),
+            MiniVaultError::Protocol(msg) => write!(f, "protocol error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MiniVaultError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MiniVaultError::Io(err) => Some(err),
+            MiniVaultError::Serde(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for MiniVaultError {
+    fn from(err: std::io::Error) -> Self {
+        MiniVaultError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for MiniVaultError {
+    fn from(err: serde_json::Error) -> Self {
+        MiniVaultError::Serde(err)
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct Health {
@@ -24,10 +108,228 @@ pub struct Health {
     pub memory_mb: i64,
 }
 
+/// TLS settings for [`MiniVaultBinary::with_tls`].
+///
+/// Leave `ca_cert_pem` unset to trust the system root store (loaded via
+/// `rustls-native-certs`). Set `client_cert_pem`/`client_key_pem` together to
+/// present a client certificate for mutual TLS.
+#[derive(Clone, Default)]
+pub struct TlsConfig {
+    pub ca_cert_pem: Option<Vec<u8>>,
+    pub client_cert_pem: Option<Vec<u8>>,
+    pub client_key_pem: Option<Vec<u8>>,
+}
+
+/// Codec used to compress values before sending them over the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Zstd,
+    Snappy,
+}
+
+impl Compression {
+    fn codec_id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zstd => 1,
+            Compression::Snappy => 2,
+        }
+    }
+
+    fn from_codec_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Zstd),
+            2 => Some(Compression::Snappy),
+            _ => None,
+        }
+    }
+}
+
+/// Controls when `set`/`set_json` compress values: `codec` is applied only
+/// once a value reaches `min_size` bytes, so small values skip the overhead
+/// of compression framing entirely.
+#[derive(Debug, Clone, Copy)]
+pub struct CompressionPolicy {
+    pub codec: Compression,
+    pub min_size: usize,
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> Self {
+        Self {
+            codec: Compression::None,
+            min_size: 0,
+        }
+    }
+}
+
+fn compress_value(codec: Compression, data: &[u8]) -> Result<Vec<u8>, MiniVaultError> {
+    let compressed = match codec {
+        Compression::None => return Ok(data.to_vec()),
+        Compression::Zstd => {
+            zstd::stream::encode_all(data, 0).map_err(|e| MiniVaultError::Protocol(e.to_string()))?
+        }
+        Compression::Snappy => snap::raw::Encoder::new()
+            .compress_vec(data)
+            .map_err(|e| MiniVaultError::Protocol(e.to_string()))?,
+    };
+
+    let mut framed = Vec::with_capacity(5 + compressed.len());
+    framed.extend_from_slice(COMPRESSED_MAGIC);
+    framed.push(codec.codec_id());
+    framed.extend_from_slice(&compressed);
+    Ok(framed)
+}
+
+/// Undoes `compress_value`'s framing. `enabled` gates the magic-prefix
+/// sniff: a value is only ever treated as compressed when this client was
+/// actually configured to compress (see `pick_codec`), so a value that
+/// happens to start with `MVC1` followed by a byte in `{0,1,2}` -- but was
+/// never compressed, because compression was never turned on -- passes
+/// through unmodified instead of being silently mangled.
+fn decompress_value(enabled: bool, data: Vec<u8>) -> Result<Vec<u8>, MiniVaultError> {
+    if !enabled || data.len() < 5 || &data[0..4] != COMPRESSED_MAGIC {
+        return Ok(data);
+    }
+
+    let codec = Compression::from_codec_id(data[4])
+        .ok_or_else(|| MiniVaultError::Protocol("unknown compression codec in value".into()))?;
+    match codec {
+        Compression::None => Ok(data[5..].to_vec()),
+        Compression::Zstd => {
+            zstd::stream::decode_all(&data[5..]).map_err(|e| MiniVaultError::Protocol(e.to_string()))
+        }
+        Compression::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(&data[5..])
+            .map_err(|e| MiniVaultError::Protocol(e.to_string())),
+    }
+}
+
+/// A connected socket, plain or TLS-wrapped, implementing `Read + Write` so
+/// the rest of the client doesn't need to care which one it has.
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.read(buf),
+            Stream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(s) => s.write(buf),
+            Stream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(s) => s.flush(),
+            Stream::Tls(s) => s.flush(),
+        }
+    }
+}
+
+/// Limits on how many already-authenticated connections `MiniVaultBinary`
+/// keeps warm between calls, and how long an idle one stays eligible for
+/// reuse before it's dropped instead of handed out.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolConfig {
+    pub max_idle: usize,
+    pub idle_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle: 4,
+            idle_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+struct PoolState {
+    idle: VecDeque<(Stream, Instant)>,
+    /// Set while some thread is dialing + authenticating a fresh connection
+    /// for the pool, so concurrent callers that also missed wait on it
+    /// instead of each opening their own socket and sending their own
+    /// OP_AUTH.
+    filling: bool,
+}
+
+struct ConnectionPool {
+    config: PoolConfig,
+    state: Mutex<PoolState>,
+    ready: Condvar,
+}
+
+impl ConnectionPool {
+    fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(PoolState {
+                idle: VecDeque::new(),
+                filling: false,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    /// Returns a still-fresh idle connection if one is available. Otherwise
+    /// the caller becomes the "leader": it returns `None` and is expected to
+    /// dial and authenticate a new connection itself, while any other
+    /// callers that also miss block in here until the leader checks a
+    /// connection back in (or gives up).
+    fn checkout(&self) -> Option<Stream> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            while let Some((stream, last_used)) = state.idle.pop_front() {
+                if last_used.elapsed() < self.config.idle_timeout {
+                    return Some(stream);
+                }
+            }
+            if !state.filling {
+                state.filling = true;
+                return None;
+            }
+            state = self.ready.wait(state).unwrap();
+        }
+    }
+
+    fn checkin(&self, stream: Stream) {
+        let mut state = self.state.lock().unwrap();
+        state.filling = false;
+        if state.idle.len() < self.config.max_idle {
+            state.idle.push_back((stream, Instant::now()));
+        }
+        self.ready.notify_all();
+    }
+
+    /// The leader failed to produce a connection; let a waiting caller take
+    /// over instead of blocking forever.
+    fn abandon_fill(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.filling = false;
+        self.ready.notify_all();
+    }
+}
+
 pub struct MiniVaultBinary {
     address: String,
     api_key: Option<String>,
     timeout: Duration,
+    tls_config: Option<Arc<ClientConfig>>,
+    compression: CompressionPolicy,
+    pool: Option<Arc<ConnectionPool>>,
 }
 
 impl MiniVaultBinary {
@@ -36,23 +338,112 @@ impl MiniVaultBinary {
             address,
             api_key,
             timeout: Duration::from_secs(5),
+            tls_config: None,
+            compression: CompressionPolicy::default(),
+            pool: None,
         }
     }
 
-    fn connect(&self) -> Result<TcpStream, Box<dyn std::error::Error>> {
-        let stream = TcpStream::connect(&self.address)?;
-        stream.set_read_timeout(Some(self.timeout))?;
-        stream.set_write_timeout(Some(self.timeout))?;
-        Ok(stream)
+    /// Compress values at or above `policy.min_size` with `policy.codec`
+    /// before sending them, and transparently decompress them on read.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = policy;
+        self
     }
 
-    fn send_request(
-        &self,
-        stream: &mut TcpStream,
-        request: &[u8],
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    /// Keep up to `config.max_idle` authenticated connections alive between
+    /// calls instead of reconnecting and re-authenticating every time.
+    pub fn with_pool(mut self, config: PoolConfig) -> Self {
+        self.pool = Some(Arc::new(ConnectionPool::new(config)));
+        self
+    }
+
+    /// Like [`Self::new`], but encrypts the connection with TLS instead of
+    /// speaking the binary protocol over a raw `TcpStream`.
+    pub fn with_tls(
+        address: String,
+        api_key: Option<String>,
+        tls: TlsConfig,
+    ) -> Result<Self, MiniVaultError> {
+        Ok(Self {
+            address,
+            api_key,
+            timeout: Duration::from_secs(5),
+            tls_config: Some(Arc::new(Self::build_tls_config(tls)?)),
+            compression: CompressionPolicy::default(),
+            pool: None,
+        })
+    }
+
+    fn build_tls_config(tls: TlsConfig) -> Result<ClientConfig, MiniVaultError> {
+        let to_protocol_err = |e: std::io::Error| MiniVaultError::Protocol(e.to_string());
+
+        let mut roots = RootCertStore::empty();
+        match &tls.ca_cert_pem {
+            Some(ca_pem) => {
+                for cert in rustls_pemfile::certs(&mut &ca_pem[..]) {
+                    roots
+                        .add(cert.map_err(to_protocol_err)?)
+                        .map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+                }
+            }
+            None => {
+                for cert in rustls_native_certs::load_native_certs().map_err(to_protocol_err)? {
+                    roots
+                        .add(cert)
+                        .map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+                }
+            }
+        }
+
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+
+        let config = match (&tls.client_cert_pem, &tls.client_key_pem) {
+            (Some(cert_pem), Some(key_pem)) => {
+                let certs = rustls_pemfile::certs(&mut &cert_pem[..])
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(to_protocol_err)?;
+                let key = rustls_pemfile::private_key(&mut &key_pem[..])
+                    .map_err(to_protocol_err)?
+                    .ok_or_else(|| {
+                        MiniVaultError::Protocol("no private key found in client_key_pem".into())
+                    })?;
+                builder
+                    .with_client_auth_cert(certs, key)
+                    .map_err(|e| MiniVaultError::Protocol(e.to_string()))?
+            }
+            _ => builder.with_no_client_auth(),
+        };
+
+        Ok(config)
+    }
+
+    fn server_name(&self) -> Result<ServerName<'static>, MiniVaultError> {
+        let host = self.address.rsplit_once(':').map_or(self.address.as_str(), |(h, _)| h);
+        ServerName::try_from(host.to_string()).map_err(|e| MiniVaultError::Protocol(e.to_string()))
+    }
+
+    fn connect(&self) -> Result<Stream, MiniVaultError> {
+        let tcp = TcpStream::connect(&self.address)?;
+        tcp.set_read_timeout(Some(self.timeout))?;
+        tcp.set_write_timeout(Some(self.timeout))?;
+
+        match &self.tls_config {
+            Some(config) => {
+                let conn = ClientConnection::new(config.clone(), self.server_name()?)
+                    .map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+                Ok(Stream::Tls(Box::new(StreamOwned::new(conn, tcp))))
+            }
+            None => Ok(Stream::Plain(tcp)),
+        }
+    }
+
+    fn send_request(&self, stream: &mut Stream, request: &[u8]) -> Result<Vec<u8>, MiniVaultError> {
         stream.write_all(request)?;
+        Self::read_response(stream)
+    }
 
+    fn read_response(stream: &mut Stream) -> Result<Vec<u8>, MiniVaultError> {
         let mut header = [0u8; 5];
         stream.read_exact(&mut header)?;
 
@@ -60,7 +451,7 @@ impl MiniVaultBinary {
         let data_len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
 
         if status != STATUS_SUCCESS {
-            return Err(format!("Server error: status=0x{:02x}", status).into());
+            return Err(MiniVaultError::from_status(status));
         }
 
         let mut data = vec![0u8; data_len];
@@ -71,7 +462,7 @@ impl MiniVaultBinary {
         Ok(data)
     }
 
-    fn authenticate(&self, stream: &mut TcpStream) -> Result<(), Box<dyn std::error::Error>> {
+    fn authenticate(&self, stream: &mut Stream) -> Result<(), MiniVaultError> {
         if let Some(api_key) = &self.api_key {
             let key_bytes = api_key.as_bytes();
             let mut request = Vec::with_capacity(3 + key_bytes.len());
@@ -84,15 +475,62 @@ impl MiniVaultBinary {
         Ok(())
     }
 
-    fn execute_operation(
-        &self,
-        op: u8,
-        key: &str,
-        value: Option<&[u8]>,
-    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    fn dial_and_authenticate(&self) -> Result<Stream, MiniVaultError> {
         let mut stream = self.connect()?;
         self.authenticate(&mut stream)?;
+        Ok(stream)
+    }
+
+    /// Hands out a ready-to-use, already-authenticated connection: a warm one
+    /// from the pool if available, otherwise a freshly dialed one.
+    fn acquire(&self) -> Result<Stream, MiniVaultError> {
+        let Some(pool) = &self.pool else {
+            return self.dial_and_authenticate();
+        };
+
+        if let Some(stream) = pool.checkout() {
+            return Ok(stream);
+        }
+
+        match self.dial_and_authenticate() {
+            Ok(stream) => Ok(stream),
+            Err(err) => {
+                pool.abandon_fill();
+                Err(err)
+            }
+        }
+    }
 
+    fn release(&self, stream: Stream) {
+        if let Some(pool) = &self.pool {
+            pool.checkin(stream);
+        }
+    }
+
+    /// Called for a connection that's known to be dead, which the caller then
+    /// drops instead of returning it to the pool. Like `release`, this clears
+    /// a pool fill in progress, so any other caller blocked in `checkout()`
+    /// gets woken up and takes over rather than blocking forever.
+    fn discard(&self) {
+        if let Some(pool) = &self.pool {
+            pool.abandon_fill();
+        }
+    }
+
+    fn is_broken_connection(err: &MiniVaultError) -> bool {
+        matches!(
+            err,
+            MiniVaultError::Io(e) if matches!(
+                e.kind(),
+                std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::UnexpectedEof
+            )
+        )
+    }
+
+    fn build_request(&self, op: u8, key: &str, value: Option<&[u8]>) -> Result<Vec<u8>, MiniVaultError> {
         let key_bytes = key.as_bytes();
         let mut request = Vec::new();
 
@@ -103,60 +541,485 @@ impl MiniVaultBinary {
                 request.extend_from_slice(key_bytes);
             }
             OP_SET => {
-                let value = value.ok_or("Value required for SET")?;
+                let value = value
+                    .ok_or_else(|| MiniVaultError::Protocol("value required for SET".into()))?;
+                let codec = self.pick_codec(value.len());
+                let payload = compress_value(codec, value)?;
+
                 request.push(op);
                 request.extend_from_slice(&(key_bytes.len() as u16).to_le_bytes());
                 request.extend_from_slice(key_bytes);
-                request.extend_from_slice(&(value.len() as u32).to_le_bytes());
-                request.push(0); // not compressed
-                request.extend_from_slice(value);
+                request.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+                request.push(codec.codec_id());
+                request.extend_from_slice(&payload);
             }
-            _ => return Err("Invalid operation".into()),
+            _ => return Err(MiniVaultError::Protocol("invalid operation".into())),
         }
 
-        self.send_request(&mut stream, &request)
+        Ok(request)
     }
 
-    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
-        let data = self.execute_operation(OP_GET, key, None)?;
-        Ok(if data.is_empty() { None } else { Some(data) })
-    }
-
-    pub fn get_json<T: for<'de> Deserialize<'de>>(
+    fn execute_operation(
         &self,
+        op: u8,
         key: &str,
-    ) -> Result<Option<T>, Box<dyn std::error::Error>> {
+        value: Option<&[u8]>,
+    ) -> Result<Vec<u8>, MiniVaultError> {
+        let request = self.build_request(op, key, value)?;
+        self.send_with_retry(&request)
+    }
+
+    /// Runs a pre-built request over a pooled connection, transparently
+    /// reconnecting once if a reused connection turns out to be dead.
+    fn send_with_retry(&self, request: &[u8]) -> Result<Vec<u8>, MiniVaultError> {
+        let mut stream = self.acquire()?;
+        match self.send_request(&mut stream, request) {
+            Ok(data) => {
+                self.release(stream);
+                Ok(data)
+            }
+            // A pooled connection can go stale between reuses (idle
+            // timeout on the server side, a dropped NAT mapping, ...);
+            // transparently reconnect once rather than surfacing that as a
+            // caller-visible error.
+            Err(err) if Self::is_broken_connection(&err) => {
+                self.discard();
+                let mut stream = self.dial_and_authenticate()?;
+                let data = self.send_request(&mut stream, request)?;
+                self.release(stream);
+                Ok(data)
+            }
+            // A response like `NotFound`/`Unauthorized`/`ServerError` means
+            // the server answered just fine -- the connection itself is
+            // still healthy, so it goes back in the pool for reuse instead
+            // of being silently dropped (which would also leave a pool fill
+            // marked in progress forever, wedging every future `acquire`).
+            Err(err) => {
+                self.release(stream);
+                Err(err)
+            }
+        }
+    }
+
+    /// Writes all `requests` back-to-back over one connection and reads the
+    /// responses back in the same order, so a batch of keys costs one
+    /// connection instead of one per key. Each response is reported
+    /// independently: a `STATUS_NOT_FOUND` on one key doesn't affect the
+    /// others in the batch.
+    fn pipeline(&self, requests: &[Vec<u8>]) -> Result<Vec<Result<Vec<u8>, MiniVaultError>>, MiniVaultError> {
+        let mut stream = self.acquire()?;
+        match Self::pipeline_once(&mut stream, requests) {
+            Ok(results) => {
+                self.release(stream);
+                Ok(results)
+            }
+            Err(err) if Self::is_broken_connection(&err) => {
+                self.discard();
+                let mut stream = self.dial_and_authenticate()?;
+                let results = Self::pipeline_once(&mut stream, requests)?;
+                self.release(stream);
+                Ok(results)
+            }
+            Err(err) => {
+                self.release(stream);
+                Err(err)
+            }
+        }
+    }
+
+    fn pipeline_once(
+        stream: &mut Stream,
+        requests: &[Vec<u8>],
+    ) -> Result<Vec<Result<Vec<u8>, MiniVaultError>>, MiniVaultError> {
+        for request in requests {
+            stream.write_all(request)?;
+        }
+
+        let mut results = Vec::with_capacity(requests.len());
+        for _ in requests {
+            match Self::read_response(stream) {
+                // A connection-level failure invalidates the rest of the
+                // batch too (the stream may now be desynced); bail out so
+                // `pipeline` can retry the whole thing on a fresh
+                // connection instead of reading garbage for the remaining
+                // responses.
+                Err(err) if Self::is_broken_connection(&err) => return Err(err),
+                other => results.push(other),
+            }
+        }
+        Ok(results)
+    }
+
+    fn pick_codec(&self, len: usize) -> Compression {
+        if len >= self.compression.min_size {
+            self.compression.codec
+        } else {
+            Compression::None
+        }
+    }
+
+    fn compression_enabled(&self) -> bool {
+        self.compression.codec != Compression::None
+    }
+
+    fn chunk_hash(data: &[u8]) -> ChunkHash {
+        use sha2::{Digest, Sha256};
+        Sha256::digest(data).into()
+    }
+
+    /// Byte offset of each chunk within the spill file, derived from the
+    /// lengths recorded while hashing.
+    fn chunk_offsets(chunk_lens: &[u64]) -> Vec<u64> {
+        let mut offsets = Vec::with_capacity(chunk_lens.len());
+        let mut pos = 0u64;
+        for &len in chunk_lens {
+            offsets.push(pos);
+            pos += len;
+        }
+        offsets
+    }
+
+    fn build_manifest_request(hashes: &[ChunkHash]) -> Vec<u8> {
+        let mut request = Vec::with_capacity(5 + hashes.len() * 32);
+        request.push(OP_CHUNK_MANIFEST);
+        request.extend_from_slice(&(hashes.len() as u32).to_le_bytes());
+        for hash in hashes {
+            request.extend_from_slice(hash);
+        }
+        request
+    }
+
+    fn parse_missing_indices(data: &[u8]) -> Result<Vec<u32>, MiniVaultError> {
+        let bad = || MiniVaultError::Protocol("malformed chunk manifest response".into());
+        let count = u32::from_le_bytes(data.get(0..4).ok_or_else(bad)?.try_into().unwrap()) as usize;
+
+        // `count` comes straight off the wire -- check it against the
+        // response we actually received before trusting it to size an
+        // allocation, so a corrupted/truncated response can't claim
+        // billions of indices and blow up `with_capacity` before the
+        // per-index bounds check below ever runs.
+        let remaining = data.len().checked_sub(4).ok_or_else(bad)?;
+        if count > remaining / 4 {
+            return Err(bad());
+        }
+
+        let mut indices = Vec::with_capacity(count);
+        let mut offset = 4;
+        for _ in 0..count {
+            let bytes = data.get(offset..offset + 4).ok_or_else(bad)?;
+            indices.push(u32::from_le_bytes(bytes.try_into().unwrap()));
+            offset += 4;
+        }
+        Ok(indices)
+    }
+
+    fn build_upload_request(hash: &ChunkHash, codec: Compression, payload: &[u8]) -> Vec<u8> {
+        let mut request = Vec::with_capacity(1 + 32 + 1 + 4 + payload.len());
+        request.push(OP_CHUNK_UPLOAD);
+        request.extend_from_slice(hash);
+        request.push(codec.codec_id());
+        request.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        request.extend_from_slice(payload);
+        request
+    }
+
+    fn build_finalize_request(key: &str, hashes: &[ChunkHash]) -> Vec<u8> {
+        let key_bytes = key.as_bytes();
+        let mut request = Vec::with_capacity(3 + key_bytes.len() + 4 + hashes.len() * 32);
+        request.push(OP_CHUNK_FINALIZE);
+        request.extend_from_slice(&(key_bytes.len() as u16).to_le_bytes());
+        request.extend_from_slice(key_bytes);
+        request.extend_from_slice(&(hashes.len() as u32).to_le_bytes());
+        for hash in hashes {
+            request.extend_from_slice(hash);
+        }
+        request
+    }
+
+    /// Splits `reader` into `CHUNK_SIZE` pieces, asks the server which ones
+    /// it's missing by hash, uploads only those, then binds `key` to the
+    /// manifest; `get` reassembles transparently. Chunks are hashed and
+    /// spilled to a temp file as `reader` is consumed rather than collected
+    /// in memory, and always uploaded raw -- framing each one with its own
+    /// `COMPRESSED_MAGIC` header would leave `get`'s single whole-value
+    /// decompress unable to make sense of the concatenated result.
+    pub fn set_chunked<R: Read>(&self, key: &str, mut reader: R) -> Result<(), MiniVaultError> {
+        let mut spill = tempfile::tempfile()?;
+        let mut hashes = Vec::new();
+        let mut chunk_lens = Vec::new();
+
+        loop {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            let mut filled = 0;
+            while filled < buf.len() {
+                match reader.read(&mut buf[filled..])? {
+                    0 => break,
+                    n => filled += n,
+                }
+            }
+            if filled == 0 {
+                break;
+            }
+            buf.truncate(filled);
+            hashes.push(Self::chunk_hash(&buf));
+            chunk_lens.push(buf.len() as u64);
+            spill.write_all(&buf)?;
+            if filled < CHUNK_SIZE {
+                break;
+            }
+        }
+
+        let manifest_request = Self::build_manifest_request(&hashes);
+        let manifest_response = self.send_with_retry(&manifest_request)?;
+        let missing = Self::parse_missing_indices(&manifest_response)?;
+
+        let offsets = Self::chunk_offsets(&chunk_lens);
+        for index in missing {
+            let index = index as usize;
+            let &offset = offsets
+                .get(index)
+                .ok_or_else(|| MiniVaultError::Protocol("server requested an out-of-range chunk index".into()))?;
+            let len = chunk_lens[index] as usize;
+
+            let mut chunk = vec![0u8; len];
+            spill.seek(SeekFrom::Start(offset))?;
+            spill.read_exact(&mut chunk)?;
+
+            let upload_request = Self::build_upload_request(&hashes[index], Compression::None, &chunk);
+            self.send_with_retry(&upload_request)?;
+        }
+
+        let finalize_request = Self::build_finalize_request(key, &hashes);
+        self.send_with_retry(&finalize_request)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, MiniVaultError> {
+        match self.execute_operation(OP_GET, key, None) {
+            Ok(data) if data.is_empty() => Ok(None),
+            Ok(data) => Ok(Some(decompress_value(self.compression_enabled(), data)?)),
+            Err(MiniVaultError::NotFound) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn get_json<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<Option<T>, MiniVaultError> {
         match self.get(key)? {
             Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
             None => Ok(None),
         }
     }
 
-    pub fn set(&self, key: &str, value: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn set(&self, key: &str, value: &[u8]) -> Result<(), MiniVaultError> {
         self.execute_operation(OP_SET, key, Some(value))?;
         Ok(())
     }
 
-    pub fn set_json<T: Serialize>(
-        &self,
-        key: &str,
-        value: &T,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn set_json<T: Serialize>(&self, key: &str, value: &T) -> Result<(), MiniVaultError> {
         let data = serde_json::to_vec(value)?;
         self.set(key, &data)
     }
 
-    pub fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn delete(&self, key: &str) -> Result<(), MiniVaultError> {
         self.execute_operation(OP_DELETE, key, None)?;
         Ok(())
     }
 
-    pub fn health(&self) -> Result<Health, Box<dyn std::error::Error>> {
+    pub fn health(&self) -> Result<Health, MiniVaultError> {
         let data = self.execute_operation(OP_HEALTH, "health", None)?;
         Ok(serde_json::from_slice(&data)?)
     }
 
-    pub fn exists(&self, key: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    pub fn exists(&self, key: &str) -> Result<bool, MiniVaultError> {
         Ok(self.get(key)?.is_some())
     }
+
+    /// Like repeated [`Self::get`] calls, but pipelines every key over one
+    /// connection instead of opening (and authenticating) one per key.
+    pub fn mget(&self, keys: &[&str]) -> Result<HashMap<String, Vec<u8>>, MiniVaultError> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let requests = keys
+            .iter()
+            .map(|key| self.build_request(OP_GET, key, None))
+            .collect::<Result<Vec<_>, _>>()?;
+        let responses = self.pipeline(&requests)?;
+
+        let mut results = HashMap::with_capacity(keys.len());
+        for (key, response) in keys.iter().zip(responses) {
+            match response {
+                Ok(data) if data.is_empty() => {}
+                Ok(data) => {
+                    results.insert(key.to_string(), decompress_value(self.compression_enabled(), data)?);
+                }
+                // A miss on one key shouldn't affect the others in the
+                // batch, exactly like the single-key `get`.
+                Err(MiniVaultError::NotFound) => {}
+                // Anything else (Unauthorized, ServerError, Protocol, ...)
+                // is a real failure and must not be mistaken for a miss.
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Like repeated [`Self::set`] calls, but pipelines every entry over one
+    /// connection instead of opening (and authenticating) one per entry.
+    pub fn mset(&self, entries: &HashMap<&str, Vec<u8>>) -> Result<(), MiniVaultError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let requests = entries
+            .iter()
+            .map(|(key, value)| self.build_request(OP_SET, key, Some(value)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let responses = self.pipeline(&requests)?;
+
+        for response in responses {
+            response?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod pool_tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn plain_stream() -> Stream {
+        // Any connected pair of sockets works for exercising pool
+        // bookkeeping; the test never reads or writes through them.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = TcpStream::connect(addr).unwrap();
+        listener.accept().unwrap();
+        Stream::Plain(client)
+    }
+
+    #[test]
+    fn checkout_reuses_checked_in_connection() {
+        let pool = ConnectionPool::new(PoolConfig::default());
+        assert!(pool.checkout().is_none(), "first checkout should be the leader");
+        pool.checkin(plain_stream());
+        assert!(pool.checkout().is_some(), "checked-in connection should be reused");
+    }
+
+    #[test]
+    fn abandon_fill_wakes_a_blocked_waiter() {
+        let pool = Arc::new(ConnectionPool::new(PoolConfig::default()));
+        assert!(pool.checkout().is_none(), "this thread becomes the leader");
+
+        let waiter_pool = Arc::clone(&pool);
+        let waiter = thread::spawn(move || waiter_pool.checkout().is_none());
+
+        // Give the waiter a moment to block in `checkout`, then abandon the
+        // fill instead of checking a connection in -- this must wake the
+        // waiter rather than leaving it blocked forever.
+        thread::sleep(Duration::from_millis(50));
+        pool.abandon_fill();
+
+        assert!(waiter.join().unwrap(), "waiter should become the new leader");
+    }
+
+    #[test]
+    fn checkin_clears_filling_even_without_a_prior_waiter() {
+        let pool = ConnectionPool::new(PoolConfig::default());
+        assert!(pool.checkout().is_none());
+        pool.checkin(plain_stream());
+        // `checkout` would still find the idle connection even with
+        // `filling` left set, so check the flag itself.
+        assert!(!pool.state.lock().unwrap().filling);
+        assert!(pool.checkout().is_some());
+    }
+}
+
+#[cfg(test)]
+mod compression_tests {
+    use super::*;
+
+    #[test]
+    fn zstd_round_trips() {
+        let data = b"the quick brown fox jumps over the lazy dog".repeat(8);
+        let framed = compress_value(Compression::Zstd, &data).unwrap();
+        assert_eq!(decompress_value(true, framed).unwrap(), data);
+    }
+
+    #[test]
+    fn snappy_round_trips() {
+        let data = b"the quick brown fox jumps over the lazy dog".repeat(8);
+        let framed = compress_value(Compression::Snappy, &data).unwrap();
+        assert_eq!(decompress_value(true, framed).unwrap(), data);
+    }
+
+    #[test]
+    fn uncompressed_value_passes_through() {
+        let data = b"hello".to_vec();
+        assert_eq!(decompress_value(true, data.clone()).unwrap(), data);
+    }
+
+    #[test]
+    fn sniff_is_skipped_when_compression_is_disabled() {
+        // A value that happens to look like a compressed frame but was
+        // never actually compressed (because this client never enabled
+        // compression) must not be run through the decoder.
+        let mut looks_compressed = COMPRESSED_MAGIC.to_vec();
+        looks_compressed.push(Compression::Zstd.codec_id());
+        looks_compressed.extend_from_slice(b"not actually zstd data");
+
+        assert_eq!(
+            decompress_value(false, looks_compressed.clone()).unwrap(),
+            looks_compressed
+        );
+    }
+}
+
+#[cfg(test)]
+mod chunk_tests {
+    use super::*;
+
+    #[test]
+    fn offsets_accumulate_chunk_lengths() {
+        let lens = [CHUNK_SIZE as u64, CHUNK_SIZE as u64, 42];
+        assert_eq!(
+            MiniVaultBinary::chunk_offsets(&lens),
+            vec![0, CHUNK_SIZE as u64, 2 * CHUNK_SIZE as u64]
+        );
+    }
+
+    #[test]
+    fn offsets_of_a_single_chunk_start_at_zero() {
+        assert_eq!(MiniVaultBinary::chunk_offsets(&[100]), vec![0]);
+    }
+
+    #[test]
+    fn offsets_of_no_chunks_is_empty() {
+        assert!(MiniVaultBinary::chunk_offsets(&[]).is_empty());
+    }
+
+    #[test]
+    fn parses_a_well_formed_manifest_response() {
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&7u32.to_le_bytes());
+        assert_eq!(MiniVaultBinary::parse_missing_indices(&data).unwrap(), vec![3, 7]);
+    }
+
+    #[test]
+    fn rejects_a_count_that_overstates_the_response_length() {
+        // Claims a huge number of indices in a response that's nowhere
+        // near long enough to hold them -- must error out instead of
+        // trying to allocate room for them up front.
+        let data = u32::MAX.to_le_bytes();
+        assert!(MiniVaultBinary::parse_missing_indices(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_a_truncated_response() {
+        assert!(MiniVaultBinary::parse_missing_indices(&[0, 0]).is_err());
+    }
 }
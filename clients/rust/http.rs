@@ -1,9 +1,121 @@
 // MiniVault HTTP Client for Rust
 
-use reqwest::{Client, StatusCode};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Certificate, Client, Identity, StatusCode};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::io::SeekFrom;
 use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// RFC 3986 path-segment safe set: everything non-alphanumeric gets
+/// percent-encoded except the unreserved marks `- . _ ~`. This keeps a key
+/// containing `/`, `?`, `#`, or spaces from corrupting the request path.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Size of each chunk `set_chunked` splits a value into before hashing and
+/// uploading it.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+/// SHA-256 digest identifying a chunk by content.
+type ChunkHash = [u8; 32];
+
+#[derive(Serialize)]
+struct ManifestRequest<'a> {
+    hashes: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct ManifestResponse {
+    missing: Vec<usize>,
+}
+
+#[derive(Serialize)]
+struct FinalizeRequest<'a> {
+    key: &'a str,
+    hashes: &'a [String],
+}
+
+fn hex_encode(hash: &ChunkHash) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Byte offset of each chunk within the spill file, derived from the
+/// lengths recorded while hashing.
+fn chunk_offsets(chunk_lens: &[u64]) -> Vec<u64> {
+    let mut offsets = Vec::with_capacity(chunk_lens.len());
+    let mut pos = 0u64;
+    for &len in chunk_lens {
+        offsets.push(pos);
+        pos += len;
+    }
+    offsets
+}
+
+/// Structured failure modes for the HTTP client, so callers can `match` on
+/// the response status a request failed with instead of string-matching a
+/// formatted message.
+#[derive(Debug)]
+pub enum MiniVaultError {
+    NotFound,
+    Unauthorized,
+    BadRequest,
+    ServerError { status: u16 },
+    Reqwest(reqwest::Error),
+    Serde(serde_json::Error),
+    Protocol(String),
+}
+
+impl MiniVaultError {
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => MiniVaultError::NotFound,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => MiniVaultError::Unauthorized,
+            StatusCode::BAD_REQUEST => MiniVaultError::BadRequest,
+            other => MiniVaultError::ServerError { status: other.as_u16() },
+        }
+    }
+}
+
+impl std::fmt::Display for MiniVaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MiniVaultError::NotFound => write!(f, "key not found"),
+            MiniVaultError::Unauthorized => write!(f, "unauthorized"),
+            MiniVaultError::BadRequest => write!(f, "bad request"),
+            MiniVaultError::ServerError { status } => write!(f, "server error: status={}", status),
+            MiniVaultError::Reqwest(err) => write!(f, "request error: {}", err),
+            MiniVaultError::Serde(err) => write!(f, "serialization error: {}", err),
+            MiniVaultError::Protocol(msg) => write!(f, "protocol error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MiniVaultError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MiniVaultError::Reqwest(err) => Some(err),
+            MiniVaultError::Serde(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for MiniVaultError {
+    fn from(err: reqwest::Error) -> Self {
+        MiniVaultError::Reqwest(err)
+    }
+}
+
+impl From<serde_json::Error> for MiniVaultError {
+    fn from(err: serde_json::Error) -> Self {
+        MiniVaultError::Serde(err)
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct Health {
@@ -16,51 +128,182 @@ pub struct Health {
     pub memory_mb: i64,
 }
 
+/// TLS settings for [`MiniVault::new`].
+///
+/// `root_cert_pem` replaces the platform's default trust store with just
+/// this root (matching the binary client's `RootCertStore::empty()` +
+/// supplied CA behavior) rather than merely adding to it -- otherwise any
+/// publicly-trusted CA could still complete the handshake, defeating the
+/// point of pinning. `client_identity_pem` (a PEM containing both cert and
+/// key) presents a client certificate for mutual TLS; this requires
+/// reqwest's `rustls-tls` feature family (`Identity::from_pem` isn't
+/// available under the default `default-tls`/native-tls backend, which
+/// expects PKCS#12 instead). `danger_accept_invalid_certs` disables
+/// certificate validation entirely and should only be used against
+/// local/dev servers.
+#[derive(Clone, Default)]
+pub struct TlsConfig {
+    pub root_cert_pem: Option<Vec<u8>>,
+    pub client_identity_pem: Option<Vec<u8>>,
+    pub danger_accept_invalid_certs: bool,
+}
+
+/// Codec used to compress values before sending them over the wire, carried
+/// on the wire as a `Content-Encoding` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Zstd,
+    Snappy,
+}
+
+impl Compression {
+    fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Zstd => Some("zstd"),
+            Compression::Snappy => Some("x-snappy"),
+        }
+    }
+
+    fn from_content_encoding(value: &str) -> Option<Self> {
+        match value {
+            "zstd" => Some(Compression::Zstd),
+            "x-snappy" => Some(Compression::Snappy),
+            _ => None,
+        }
+    }
+}
+
+/// Controls when `set`/`set_json` compress values: `codec` is applied only
+/// once a value reaches `min_size` bytes, so small values skip the overhead
+/// of compression entirely.
+#[derive(Debug, Clone, Copy)]
+pub struct CompressionPolicy {
+    pub codec: Compression,
+    pub min_size: usize,
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> Self {
+        Self {
+            codec: Compression::None,
+            min_size: 0,
+        }
+    }
+}
+
+fn compress_value(codec: Compression, data: Vec<u8>) -> Result<Vec<u8>, MiniVaultError> {
+    match codec {
+        Compression::None => Ok(data),
+        Compression::Zstd => {
+            zstd::stream::encode_all(&data[..], 0).map_err(|e| MiniVaultError::Protocol(e.to_string()))
+        }
+        Compression::Snappy => snap::raw::Encoder::new()
+            .compress_vec(&data)
+            .map_err(|e| MiniVaultError::Protocol(e.to_string())),
+    }
+}
+
+fn decompress_value(codec: Compression, data: Vec<u8>) -> Result<Vec<u8>, MiniVaultError> {
+    match codec {
+        Compression::None => Ok(data),
+        Compression::Zstd => {
+            zstd::stream::decode_all(&data[..]).map_err(|e| MiniVaultError::Protocol(e.to_string()))
+        }
+        Compression::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(&data)
+            .map_err(|e| MiniVaultError::Protocol(e.to_string())),
+    }
+}
+
 pub struct MiniVault {
     base_url: String,
     api_key: Option<String>,
     client: Client,
+    compression: CompressionPolicy,
 }
 
 impl MiniVault {
-    pub fn new(base_url: String, api_key: Option<String>) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(5))
-            .build()
-            .unwrap();
+    pub fn new(
+        base_url: String,
+        api_key: Option<String>,
+        tls: Option<TlsConfig>,
+    ) -> Result<Self, MiniVaultError> {
+        let mut builder = Client::builder().timeout(Duration::from_secs(5));
 
-        Self {
+        if let Some(tls) = tls {
+            if let Some(root_pem) = &tls.root_cert_pem {
+                // `add_root_certificate` alone only *adds* to reqwest's
+                // default system trust store, so it doesn't actually pin --
+                // disable the built-in roots too or any other public CA
+                // still completes the handshake.
+                builder = builder
+                    .add_root_certificate(Certificate::from_pem(root_pem)?)
+                    .tls_built_in_root_certs(false);
+            }
+            if let Some(identity_pem) = &tls.client_identity_pem {
+                builder = builder.identity(Identity::from_pem(identity_pem)?);
+            }
+            if tls.danger_accept_invalid_certs {
+                builder = builder.danger_accept_invalid_certs(true);
+            }
+        }
+
+        let client = builder.build()?;
+
+        Ok(Self {
             base_url: base_url.trim_end_matches('/').to_string(),
             api_key,
             client,
-        }
+            compression: CompressionPolicy::default(),
+        })
     }
 
-    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
-        let url = format!("{}/{}", self.base_url, key);
+    /// Compress values at or above `policy.min_size` with `policy.codec`
+    /// before sending them, and transparently decompress them on read.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = policy;
+        self
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, MiniVaultError> {
+        let url = format!("{}/{}", self.base_url, utf8_percent_encode(key, PATH_SEGMENT));
         let response = self.client.get(&url).send().await?;
 
         match response.status() {
-            StatusCode::OK => Ok(Some(response.bytes().await?.to_vec())),
+            StatusCode::OK => {
+                let codec = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_ENCODING)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(Compression::from_content_encoding)
+                    .unwrap_or(Compression::None);
+                let data = response.bytes().await?.to_vec();
+                Ok(Some(decompress_value(codec, data)?))
+            }
             StatusCode::NOT_FOUND => Ok(None),
-            status => Err(format!("GET failed: {}", status).into()),
+            status => Err(MiniVaultError::from_status(status)),
         }
     }
 
-    pub async fn get_json<T: for<'de> Deserialize<'de>>(
-        &self,
-        key: &str,
-    ) -> Result<Option<T>, Box<dyn std::error::Error>> {
+    pub async fn get_json<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<Option<T>, MiniVaultError> {
         match self.get(key).await? {
             Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
             None => Ok(None),
         }
     }
 
-    pub async fn set(&self, key: &str, data: Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/{}", self.base_url, key);
-        let mut request = self.client.put(&url).body(data);
+    pub async fn set(&self, key: &str, data: Vec<u8>) -> Result<(), MiniVaultError> {
+        let url = format!("{}/{}", self.base_url, utf8_percent_encode(key, PATH_SEGMENT));
+        let codec = self.pick_codec(data.len());
+        let payload = compress_value(codec, data)?;
+
+        let mut request = self.client.put(&url).body(payload);
 
+        if let Some(encoding) = codec.content_encoding() {
+            request = request.header(reqwest::header::CONTENT_ENCODING, encoding);
+        }
         if let Some(api_key) = &self.api_key {
             request = request.header("X-API-Key", api_key);
         }
@@ -68,23 +311,125 @@ impl MiniVault {
         let response = request.send().await?;
 
         if !response.status().is_success() {
-            return Err(format!("SET failed: {}", response.status()).into());
+            return Err(MiniVaultError::from_status(response.status()));
         }
 
         Ok(())
     }
 
-    pub async fn set_json<T: Serialize>(
+    fn pick_codec(&self, len: usize) -> Compression {
+        if len >= self.compression.min_size {
+            self.compression.codec
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Splits `reader` into `CHUNK_SIZE` pieces, asks the server which ones
+    /// it's missing by hash, uploads only those, then binds `key` to the
+    /// manifest; `get` reassembles transparently. Chunks are hashed and
+    /// spilled to a temp file as `reader` is consumed rather than collected
+    /// in memory, and always uploaded raw -- a reassembled value can only
+    /// carry one `Content-Encoding`, so independently compressing each
+    /// chunk isn't an option here.
+    pub async fn set_chunked<R: tokio::io::AsyncRead + Unpin>(
         &self,
         key: &str,
-        value: &T,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        mut reader: R,
+    ) -> Result<(), MiniVaultError> {
+        let spill = tempfile::tempfile().map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+        let mut spill = tokio::fs::File::from_std(spill);
+
+        let mut hashes: Vec<ChunkHash> = Vec::new();
+        let mut chunk_lens: Vec<u64> = Vec::new();
+
+        loop {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            let mut filled = 0;
+            while filled < buf.len() {
+                let n = reader
+                    .read(&mut buf[filled..])
+                    .await
+                    .map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+                if n == 0 {
+                    break;
+                }
+                filled += n;
+            }
+            if filled == 0 {
+                break;
+            }
+            buf.truncate(filled);
+            hashes.push(Sha256::digest(&buf).into());
+            chunk_lens.push(buf.len() as u64);
+            spill
+                .write_all(&buf)
+                .await
+                .map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+            if filled < CHUNK_SIZE {
+                break;
+            }
+        }
+
+        let hash_hexes: Vec<String> = hashes.iter().map(hex_encode).collect();
+
+        let manifest_url = format!("{}/_chunks/manifest", self.base_url);
+        let response = self
+            .client
+            .post(&manifest_url)
+            .json(&ManifestRequest { hashes: &hash_hexes })
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(MiniVaultError::from_status(response.status()));
+        }
+        let manifest: ManifestResponse = response.json().await?;
+
+        let offsets = chunk_offsets(&chunk_lens);
+        for index in manifest.missing {
+            let &offset = offsets
+                .get(index)
+                .ok_or_else(|| MiniVaultError::Protocol("server requested an out-of-range chunk index".into()))?;
+            let len = chunk_lens[index] as usize;
+
+            let mut chunk = vec![0u8; len];
+            spill
+                .seek(SeekFrom::Start(offset))
+                .await
+                .map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+            spill
+                .read_exact(&mut chunk)
+                .await
+                .map_err(|e| MiniVaultError::Protocol(e.to_string()))?;
+
+            let url = format!("{}/_chunks/{}", self.base_url, hash_hexes[index]);
+            let response = self.client.put(&url).body(chunk).send().await?;
+            if !response.status().is_success() {
+                return Err(MiniVaultError::from_status(response.status()));
+            }
+        }
+
+        let finalize_url = format!("{}/_chunks/finalize", self.base_url);
+        let response = self
+            .client
+            .post(&finalize_url)
+            .json(&FinalizeRequest { key, hashes: &hash_hexes })
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(MiniVaultError::from_status(response.status()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_json<T: Serialize>(&self, key: &str, value: &T) -> Result<(), MiniVaultError> {
         let data = serde_json::to_vec(value)?;
         self.set(key, data).await
     }
 
-    pub async fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let url = format!("{}/{}", self.base_url, key);
+    pub async fn delete(&self, key: &str) -> Result<(), MiniVaultError> {
+        let url = format!("{}/{}", self.base_url, utf8_percent_encode(key, PATH_SEGMENT));
         let mut request = self.client.delete(&url);
 
         if let Some(api_key) = &self.api_key {
@@ -94,33 +439,36 @@ impl MiniVault {
         let response = request.send().await?;
 
         if !response.status().is_success() {
-            return Err(format!("DELETE failed: {}", response.status()).into());
+            return Err(MiniVaultError::from_status(response.status()));
         }
 
         Ok(())
     }
 
-    pub async fn exists(&self, key: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    pub async fn exists(&self, key: &str) -> Result<bool, MiniVaultError> {
         Ok(self.get(key).await?.is_some())
     }
 
-    pub async fn health(&self) -> Result<Health, Box<dyn std::error::Error>> {
+    pub async fn health(&self) -> Result<Health, MiniVaultError> {
         let url = format!("{}/health", self.base_url);
         let response = self.client.get(&url).send().await?;
 
         if !response.status().is_success() {
-            return Err(format!("Health check failed: {}", response.status()).into());
+            return Err(MiniVaultError::from_status(response.status()));
         }
 
         Ok(response.json().await?)
     }
 
-    pub async fn mget(&self, keys: &[&str]) -> Result<HashMap<String, Vec<u8>>, Box<dyn std::error::Error>> {
+    pub async fn mget(&self, keys: &[&str]) -> Result<HashMap<String, Vec<u8>>, MiniVaultError> {
         let mut results = HashMap::new();
         let futures: Vec<_> = keys.iter().map(|key| self.get(key)).collect();
 
         for (i, result) in futures::future::join_all(futures).await.into_iter().enumerate() {
-            if let Ok(Some(data)) = result {
+            // `get` already maps NotFound to Ok(None); anything else (an
+            // Unauthorized/ServerError/Protocol failure on one key) must
+            // not be mistaken for a miss.
+            if let Some(data) = result? {
                 results.insert(keys[i].to_string(), data);
             }
         }
@@ -128,7 +476,7 @@ impl MiniVault {
         Ok(results)
     }
 
-    pub async fn mset(&self, entries: &HashMap<&str, Vec<u8>>) -> Result<(), Box<dyn std::error::Error>> {
+    pub async fn mset(&self, entries: &HashMap<&str, Vec<u8>>) -> Result<(), MiniVaultError> {
         let futures: Vec<_> = entries
             .iter()
             .map(|(key, value)| self.set(key, value.clone()))
@@ -141,3 +489,45 @@ impl MiniVault {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod path_segment_tests {
+    use super::*;
+
+    #[test]
+    fn unreserved_marks_pass_through_unescaped() {
+        let key = "user-123.v2_final~1";
+        assert_eq!(utf8_percent_encode(key, PATH_SEGMENT).to_string(), key);
+    }
+
+    #[test]
+    fn path_separators_and_spaces_get_escaped() {
+        let encoded = utf8_percent_encode("a/b c", PATH_SEGMENT).to_string();
+        assert_eq!(encoded, "a%2Fb%20c");
+    }
+
+    #[test]
+    fn alphanumeric_round_trips() {
+        let key = "Key42";
+        assert_eq!(utf8_percent_encode(key, PATH_SEGMENT).to_string(), key);
+    }
+}
+
+#[cfg(test)]
+mod chunk_tests {
+    use super::*;
+
+    #[test]
+    fn offsets_accumulate_chunk_lengths() {
+        let lens = [CHUNK_SIZE as u64, CHUNK_SIZE as u64, 42];
+        assert_eq!(
+            chunk_offsets(&lens),
+            vec![0, CHUNK_SIZE as u64, 2 * CHUNK_SIZE as u64]
+        );
+    }
+
+    #[test]
+    fn offsets_of_no_chunks_is_empty() {
+        assert!(chunk_offsets(&[]).is_empty());
+    }
+}